@@ -0,0 +1,89 @@
+// Copyright 2020 The Chromium OS Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A generic, cross-thread abort mechanism for an in-flight top-level future, so a control thread
+//! can tear down a device's task (e.g. on VM shutdown) without waiting for it to reach a natural
+//! await point. Used by `join_handle::spawn` to implement cancel-on-drop for its `JoinHandle`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+struct Inner {
+    aborted: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+/// The aborted future's `AbortHandle::abort` was called before it completed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Aborted;
+
+/// Wraps a future so it resolves to `Err(Aborted)` as soon as the paired `AbortHandle::abort` is
+/// called, instead of running to completion. Create one with `abortable`.
+pub struct Abortable<F> {
+    future: F,
+    inner: Arc<Inner>,
+}
+
+impl<F: Future + Unpin> Future for Abortable<F> {
+    type Output = Result<F::Output, Aborted>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.inner.aborted.load(Ordering::Acquire) {
+            return Poll::Ready(Err(Aborted));
+        }
+
+        *this.inner.waker.lock().unwrap() = Some(cx.waker().clone());
+
+        match Pin::new(&mut this.future).poll(cx) {
+            Poll::Ready(output) => Poll::Ready(Ok(output)),
+            Poll::Pending => {
+                // `abort` may have been called concurrently with the poll above; check again so
+                // a race doesn't leave this future parked forever with no waker left to fire.
+                if this.inner.aborted.load(Ordering::Acquire) {
+                    Poll::Ready(Err(Aborted))
+                } else {
+                    Poll::Pending
+                }
+            }
+        }
+    }
+}
+
+/// A handle that aborts the `Abortable` future it was created alongside. `Clone + Send` so a
+/// control thread can hold one and abort a task owned by the executor's thread.
+#[derive(Clone)]
+pub struct AbortHandle {
+    inner: Arc<Inner>,
+}
+
+impl AbortHandle {
+    /// Causes the associated `Abortable` to resolve to `Err(Aborted)` and wakes its task so the
+    /// executor re-polls it promptly, even if no FD event is pending.
+    pub fn abort(&self) {
+        self.inner.aborted.store(true, Ordering::Release);
+        if let Some(waker) = self.inner.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Wraps `fut` so it can be aborted from another thread via the returned `AbortHandle`, even
+/// while it is parked waiting on an FD.
+pub fn abortable<F: Future>(fut: F) -> (Abortable<F>, AbortHandle) {
+    let inner = Arc::new(Inner {
+        aborted: AtomicBool::new(false),
+        waker: Mutex::new(None),
+    });
+    (
+        Abortable {
+            future: fut,
+            inner: inner.clone(),
+        },
+        AbortHandle { inner },
+    )
+}
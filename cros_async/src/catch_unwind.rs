@@ -0,0 +1,52 @@
+// Copyright 2020 The Chromium OS Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Isolates a panic inside a single future so it doesn't unwind through `Executor::run` and take
+//! down every other device future sharing the executor.
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Wraps `fut` so that a panic during any of its polls is caught instead of unwinding past
+/// `CatchUnwind`, resolving to `Err(payload)` instead. Created with `catch_unwind`.
+pub struct CatchUnwind<F: Future> {
+    inner: Option<F>,
+}
+
+impl<F: Future + Unpin> Future for CatchUnwind<F> {
+    type Output = std::thread::Result<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let inner = match &mut this.inner {
+            Some(inner) => inner,
+            // A previous poll already completed, either normally or by catching a panic; per the
+            // `Future` contract polling again is allowed to do anything, so we just panic rather
+            // than polling the inner future again.
+            None => panic!("CatchUnwind polled after it already completed"),
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(|| Pin::new(inner).poll(cx))) {
+            Ok(Poll::Ready(output)) => {
+                this.inner = None;
+                Poll::Ready(Ok(output))
+            }
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => {
+                this.inner = None;
+                Poll::Ready(Err(payload))
+            }
+        }
+    }
+}
+
+/// Wraps `fut` so that if it panics while being polled, the panic is caught and turned into
+/// `Err(payload)` instead of unwinding through the executor and aborting every other future it is
+/// running. Intended to be applied to each device's top-level future so one misbehaving device
+/// can't kill the whole VMM.
+pub fn catch_unwind<F: Future>(fut: F) -> CatchUnwind<F> {
+    CatchUnwind { inner: Some(fut) }
+}
@@ -0,0 +1,70 @@
+// Copyright 2020 The Chromium OS Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! `FutureList` implementations backing the `complete*` family of executor constructors.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::task::noop_waker_ref;
+
+use crate::executor::{ExecutableFuture, FutureList};
+
+/// Runs an arbitrary number of futures until every one of them has completed, returning their
+/// outputs in the same order as the input `Vec`. Useful when the number of top-level futures
+/// (e.g. one per virtio queue) is only known at runtime, unlike the fixed-arity
+/// `complete2`..`complete5`.
+pub(crate) struct CompleteAll<F: Future + Unpin> {
+    futures: Vec<Option<F>>,
+    results: Vec<Option<F::Output>>,
+    new_futures: VecDeque<ExecutableFuture<()>>,
+}
+
+impl<F: Future + Unpin> CompleteAll<F> {
+    pub fn new(futures: Vec<F>) -> CompleteAll<F> {
+        let len = futures.len();
+        CompleteAll {
+            futures: futures.into_iter().map(Some).collect(),
+            results: (0..len).map(|_| None).collect(),
+            new_futures: VecDeque::new(),
+        }
+    }
+}
+
+impl<F: Future + Unpin> FutureList for CompleteAll<F> {
+    type Output = Vec<F::Output>;
+
+    fn poll_results(&mut self) -> Option<Self::Output> {
+        let waker = noop_waker_ref();
+        let mut cx = Context::from_waker(waker);
+
+        for (slot, result) in self.futures.iter_mut().zip(self.results.iter_mut()) {
+            if result.is_some() {
+                continue;
+            }
+            if let Some(fut) = slot {
+                if let Poll::Ready(output) = Pin::new(fut).poll(&mut cx) {
+                    *result = Some(output);
+                    *slot = None;
+                }
+            }
+        }
+
+        if self.results.iter().all(Option::is_some) {
+            Some(self.results.iter_mut().map(|r| r.take().unwrap()).collect())
+        } else {
+            None
+        }
+    }
+
+    fn any_ready(&self) -> bool {
+        false
+    }
+
+    fn futures_mut(&mut self) -> &mut VecDeque<ExecutableFuture<()>> {
+        &mut self.new_futures
+    }
+}
@@ -1,15 +1,40 @@
-use crate::{new, AsyncResult, IoSourceExt};
-use std::os::unix::io::AsRawFd;
+use crate::{
+    add_read_op, add_read_waker, add_write_op, add_write_waker, cancel_waker, new, take_result,
+    use_uring, AsyncResult, Error as CrateError, IoSourceExt, WakerToken,
+};
+use futures_io::{AsyncRead, AsyncWrite};
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// Tracks what, if anything, a pending `poll_read`/`poll_write` is waiting on.
+enum IoState {
+    Idle,
+    // fd executor fallback: waiting for a readiness notification, then a raw syscall is retried.
+    Waiting(WakerToken),
+    // uring executor: a completion op was submitted and is awaiting its result.
+    Submitted(WakerToken),
+}
 
 /// An async version of sys_util::EventFd.
 pub struct EventAsync<'a, F: AsRawFd + 'a> {
+    fd: RawFd,
     io_source: Box<dyn IoSourceExt<F> + 'a>,
+    read_state: IoState,
+    write_state: IoState,
 }
 
 impl<'a, F: AsRawFd + 'a> EventAsync<'a, F> {
     #[allow(dead_code)]
     pub fn new(f: F) -> AsyncResult<EventAsync<'a, F>> {
-        Ok(EventAsync { io_source: new(f)? })
+        let fd = f.as_raw_fd();
+        Ok(EventAsync {
+            fd,
+            io_source: new(f)?,
+            read_state: IoState::Idle,
+            write_state: IoState::Idle,
+        })
     }
 
     #[allow(dead_code)]
@@ -18,6 +43,147 @@ impl<'a, F: AsRawFd + 'a> EventAsync<'a, F> {
     }
 }
 
+impl<'a, F: AsRawFd + Unpin + 'a> AsyncRead for EventAsync<'a, F> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        // When the uring executor is running, go through the same completion-op machinery that
+        // `IoSourceExt` uses instead of a readiness notification plus a raw syscall, so this read
+        // is a single ring round-trip rather than two.
+        if use_uring() {
+            return this.poll_read_uring(cx, buf);
+        }
+
+        // Safe because `fd` is owned by `this` for its lifetime and `buf` is valid for the
+        // duration of the call.
+        let ret = unsafe { libc::read(this.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if ret >= 0 {
+            this.read_state = IoState::Idle;
+            return Poll::Ready(Ok(ret as usize));
+        }
+
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::WouldBlock {
+            return Poll::Ready(Err(err));
+        }
+
+        let token = add_read_waker(this.fd, cx.waker().clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        this.read_state = IoState::Waiting(token);
+        Poll::Pending
+    }
+}
+
+impl<'a, F: AsRawFd + Unpin + 'a> EventAsync<'a, F> {
+    fn poll_read_uring(&mut self, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let token = match std::mem::replace(&mut self.read_state, IoState::Idle) {
+            IoState::Idle => {
+                let len = buf.len();
+                add_read_op(self.fd, 0, vec![0u8; len], cx.waker().clone())
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            }
+            IoState::Submitted(token) => token,
+            IoState::Waiting(_) => unreachable!("uring and fd-executor read states can't mix"),
+        };
+
+        match take_result(token) {
+            Ok((res, filled)) if res >= 0 => {
+                let len = res as usize;
+                buf[..len].copy_from_slice(&filled[..len]);
+                Poll::Ready(Ok(len))
+            }
+            Ok((res, _)) => Poll::Ready(Err(io::Error::from_raw_os_error(-res as i32))),
+            Err(CrateError::URingExecutor(crate::uring_executor::Error::NoResultAvailable)) => {
+                self.read_state = IoState::Submitted(token);
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+        }
+    }
+
+    fn poll_write_uring(&mut self, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let token = match std::mem::replace(&mut self.write_state, IoState::Idle) {
+            IoState::Idle => add_write_op(self.fd, 0, buf.to_vec(), cx.waker().clone())
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?,
+            IoState::Submitted(token) => token,
+            IoState::Waiting(_) => unreachable!("uring and fd-executor write states can't mix"),
+        };
+
+        match take_result(token) {
+            Ok((res, _)) if res >= 0 => Poll::Ready(Ok(res as usize)),
+            Ok((res, _)) => Poll::Ready(Err(io::Error::from_raw_os_error(-res as i32))),
+            Err(CrateError::URingExecutor(crate::uring_executor::Error::NoResultAvailable)) => {
+                self.write_state = IoState::Submitted(token);
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+        }
+    }
+}
+
+impl<'a, F: AsRawFd + Unpin + 'a> AsyncWrite for EventAsync<'a, F> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        // See `poll_read`'s comment: when the uring executor is running, submit a completion op
+        // through the same path `IoSourceExt` uses instead of a readiness notification.
+        if use_uring() {
+            return this.poll_write_uring(cx, buf);
+        }
+
+        // Safe because `fd` is owned by `this` for its lifetime and `buf` is valid for the
+        // duration of the call.
+        let ret = unsafe { libc::write(this.fd, buf.as_ptr() as *const libc::c_void, buf.len()) };
+        if ret >= 0 {
+            this.write_state = IoState::Idle;
+            return Poll::Ready(Ok(ret as usize));
+        }
+
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::WouldBlock {
+            return Poll::Ready(Err(err));
+        }
+
+        let token = add_write_waker(this.fd, cx.waker().clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        this.write_state = IoState::Waiting(token);
+        Poll::Pending
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        // EventFds and similar fds have no userspace buffering to flush.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, F: AsRawFd + 'a> Drop for EventAsync<'a, F> {
+    fn drop(&mut self) {
+        // Dropping with a `Submitted` op in flight (e.g. because this `EventAsync` lost a
+        // `select`/`timeout` race) is safe: `cancel_waker` keeps the completion op's buffer and
+        // duped fd alive in the uring executor until the kernel's completion event actually
+        // arrives, rather than freeing them out from under the in-flight read or write.
+        for state in [
+            std::mem::replace(&mut self.read_state, IoState::Idle),
+            std::mem::replace(&mut self.write_state, IoState::Idle),
+        ] {
+            match state {
+                IoState::Idle => {}
+                IoState::Waiting(token) | IoState::Submitted(token) => {
+                    let _ = cancel_waker(token);
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
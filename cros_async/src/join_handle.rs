@@ -0,0 +1,95 @@
+// Copyright 2020 The Chromium OS Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! `spawn` and the `JoinHandle` it returns, letting a side task's result be collected later
+//! instead of forcing device setup code into one large monolithic top-level future.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::abortable::{abortable, AbortHandle};
+use crate::{add_future, Result};
+
+// State shared between the spawned future's completion adapter and the `JoinHandle`.
+struct Shared<T> {
+    output: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// A handle to a future spawned onto the executor's top-level task list with `spawn`. Awaiting
+/// the handle resolves to the spawned future's output once it completes.
+///
+/// Dropping the handle without calling `detach` aborts the underlying future instead of letting
+/// it run to completion; call `detach` to let it keep running unobserved.
+pub struct JoinHandle<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    abort_handle: AbortHandle,
+    detached: bool,
+}
+
+impl<T> JoinHandle<T> {
+    /// Lets the spawned future keep running to completion even if this handle is dropped.
+    pub fn detach(mut self) {
+        self.detached = true;
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        if !self.detached {
+            self.abort_handle.abort();
+        }
+    }
+}
+
+/// Adds `fut` to the executor's top-level task list and returns a `JoinHandle` that resolves to
+/// its output once it completes, so side tasks no longer have to be folded into one giant future
+/// just to observe their result.
+pub fn spawn<F>(fut: F) -> Result<JoinHandle<F::Output>>
+where
+    F: Future + 'static,
+{
+    let shared = Arc::new(Mutex::new(Shared {
+        output: None,
+        waker: None,
+    }));
+
+    let (abortable_fut, abort_handle) = abortable(Box::pin(fut));
+
+    let adapter_shared = shared.clone();
+    let adapter = async move {
+        if let Ok(output) = abortable_fut.await {
+            let mut shared = adapter_shared.lock().unwrap();
+            shared.output = Some(output);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    };
+
+    add_future(Box::pin(adapter))?;
+
+    Ok(JoinHandle {
+        shared,
+        abort_handle,
+        detached: false,
+    })
+}
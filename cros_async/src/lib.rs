@@ -53,15 +53,25 @@
 //! [`add_future`](fn.add_future.html) - Used to add a new future to the top-level list of running
 //! futures.
 
+mod abortable;
+mod catch_unwind;
 mod complete;
 mod executor;
 mod fd_executor;
+mod join_handle;
 mod select;
+mod shared;
+mod timer;
 mod uring_executor;
 mod waker;
 
+pub use abortable::{abortable, AbortHandle, Abortable, Aborted};
+pub use catch_unwind::{catch_unwind, CatchUnwind};
 pub use executor::{Executor, WakerToken};
+pub use join_handle::{spawn, JoinHandle};
 pub use select::SelectResult;
+pub use shared::{shared, Shared};
+pub use timer::{timeout, Elapsed, TimerAsync};
 
 use executor::{FutureList, RunOne};
 use fd_executor::FdExecutor;
@@ -80,6 +90,12 @@ pub enum Error {
     FdExecutor(fd_executor::Error),
     /// Error from the uring executor.
     URingExecutor(uring_executor::Error),
+    /// Error from a `TimerAsync`.
+    Timer(timer::Error),
+    /// `select_all` or `select_ok` was given an empty `Vec` of futures, so there is nothing to
+    /// run and, for `select_ok`, no error to report if everything "failed". `complete_all`
+    /// returns an empty `Vec` for empty input instead.
+    EmptyFutureVec,
 }
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -90,6 +106,8 @@ impl Display for Error {
         match self {
             FdExecutor(e) => write!(f, "Failure in the FD executor: {}", e),
             URingExecutor(e) => write!(f, "Failure in the uring executor: {}", e),
+            Timer(e) => write!(f, "Failure in a timer: {}", e),
+            EmptyFutureVec => write!(f, "Cannot run an empty Vec of futures"),
         }
     }
 }
@@ -346,6 +364,63 @@ pub fn select6<
     run_executor(select::Select6::new(f1, f2, f3, f4, f5, f6))
 }
 
+/// Creates an executor that runs an arbitrary number of futures until one of them completes,
+/// returning its output, its index in `futures`, and the still-pending remainder. Unlike
+/// `select2`..`select6`, the number of futures doesn't need to be known until runtime, which suits
+/// a VMM with a runtime-determined number of virtio queues or device futures.
+///
+///  # Example
+///
+///    ```
+///    use cros_async::select_all;
+///    use futures::future::pending;
+///    use futures::FutureExt;
+///
+///    let first = async { 5 }.boxed();
+///    let second = async { let () = pending().await; 0 }.boxed();
+///    match select_all(vec![first, second]) {
+///        Ok((5, 0, _remaining)) => (),
+///        _ => panic!("select_all didn't return the first future"),
+///    };
+///    ```
+pub fn select_all<F: Future + Unpin>(futures: Vec<F>) -> Result<(F::Output, usize, Vec<F>)> {
+    if futures.is_empty() {
+        // Nothing to select between, and nothing would ever be registered to wake the executor,
+        // so run_executor would just block forever; reject up front instead.
+        return Err(Error::EmptyFutureVec);
+    }
+    run_executor(select::SelectAll::new(futures))
+}
+
+/// Runs every future in `futures` until one resolves to `Ok`, returning its output plus the
+/// still-pending remainder. Futures that resolve to `Err` are dropped and only surfaced if every
+/// future in the set fails, at which point the last error observed is returned. Handy for
+/// device-probe or connection futures where the caller just wants the first success.
+///
+///  # Example
+///
+///    ```
+///    use cros_async::select_ok;
+///    use futures::FutureExt;
+///
+///    let first = async { Err(()) }.boxed();
+///    let second = async { Ok(5) }.boxed();
+///    let (result, _remaining) = select_ok(vec![first, second]).unwrap().unwrap();
+///    assert_eq!(result, 5);
+///    ```
+pub fn select_ok<F, T, E>(futures: Vec<F>) -> Result<std::result::Result<(T, Vec<F>), E>>
+where
+    F: Future<Output = std::result::Result<T, E>> + Unpin,
+{
+    if futures.is_empty() {
+        // There is no success to wait for and, with no future having failed, no `E` to report
+        // either; reject up front rather than relying on an invariant that only holds for
+        // non-empty input.
+        return Err(Error::EmptyFutureVec);
+    }
+    run_executor(select::SelectOk::new(futures))
+}
+
 // Combination helpers to run until all futures are complete.
 
 /// Creates an executor that runs the two given futures to completion, returning a tuple of the
@@ -461,6 +536,24 @@ pub fn complete5<
     run_executor(complete::Complete5::new(f1, f2, f3, f4, f5))
 }
 
+/// Creates an executor that runs an arbitrary number of futures to completion, returning their
+/// outputs in the same order as `futures`. Unlike `complete2`..`complete5`, the number of futures
+/// doesn't need to be known until runtime.
+///
+///  # Example
+///
+///    ```
+///    use cros_async::complete_all;
+///    use futures::FutureExt;
+///
+///    let first = async { 5 }.boxed();
+///    let second = async { 6 }.boxed();
+///    assert_eq!(complete_all(vec![first, second]).unwrap(), vec![5, 6]);
+///    ```
+pub fn complete_all<F: Future + Unpin>(futures: Vec<F>) -> Result<Vec<F::Output>> {
+    run_executor(complete::CompleteAll::new(futures))
+}
+
 // Functions to be used by `Future` implementations
 
 /// Tells the waking system to wake `waker` when `fd` becomes readable.
@@ -498,6 +591,38 @@ pub fn cancel_waker(token: WakerToken) -> Result<()> {
     }
 }
 
+/// Submits a `readv` of `fd` at `offset` into `buf` and wakes `waker` once it completes, instead
+/// of only signalling readiness. Ownership of `buf` is transferred to the executor so it can't be
+/// dropped or reused while the kernel still holds a pointer to it; it is handed back by
+/// `take_result`. Only available when the uring executor is in use; use `add_read_waker` and a
+/// regular `read` call to support the FD executor as well.
+pub fn add_read_op(fd: RawFd, offset: u64, buf: Vec<u8>, waker: Waker) -> Result<WakerToken> {
+    uring_executor::add_read_op(fd, offset, buf, waker).map_err(Error::URingExecutor)
+}
+
+/// Submits a `writev` of `fd` at `offset` from `buf` and wakes `waker` once it completes, instead
+/// of only signalling readiness. Ownership of `buf` is transferred to the executor for the same
+/// reason as `add_read_op`; it is handed back by `take_result`. Only available when the uring
+/// executor is in use; use `add_write_waker` and a regular `write` call to support the FD executor
+/// as well.
+pub fn add_write_op(fd: RawFd, offset: u64, buf: Vec<u8>, waker: Waker) -> Result<WakerToken> {
+    uring_executor::add_write_op(fd, offset, buf, waker).map_err(Error::URingExecutor)
+}
+
+/// Submits an `fsync` of `fd` and wakes `waker` once it completes. Only available when the uring
+/// executor is in use.
+pub fn add_fsync(fd: RawFd, waker: Waker) -> Result<WakerToken> {
+    uring_executor::add_fsync(fd, waker).map_err(Error::URingExecutor)
+}
+
+/// Returns the `res` value of the completion op identified by `token` along with the buffer that
+/// was passed to `add_read_op`/`add_write_op` (empty for `add_fsync`), consuming the result. If
+/// the op has not completed yet, returns an `Error::URingExecutor(NoResultAvailable)` error and
+/// the op stays registered, so `take_result` can be called again after the waker is woken.
+pub fn take_result(token: WakerToken) -> Result<(i64, Vec<u8>)> {
+    uring_executor::take_result(token).map_err(Error::URingExecutor)
+}
+
 /// Adds a new top level future to the Executor.
 /// These futures must return `()`, indicating they are intended to create side-effects only.
 pub fn add_future(future: Pin<Box<dyn Future<Output = ()>>>) -> Result<()> {
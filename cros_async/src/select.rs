@@ -0,0 +1,143 @@
+// Copyright 2020 The Chromium OS Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! `FutureList` implementations backing the `select*` family of executor constructors.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::task::noop_waker_ref;
+
+use crate::executor::{ExecutableFuture, FutureList};
+
+/// The result of polling a single future passed to one of the `select*` functions: either its
+/// output, or the future itself if it is still pending.
+pub enum SelectResult<F: Future> {
+    Pending(F),
+    Finished(F::Output),
+}
+
+/// Runs an arbitrary number of futures until the first one completes, returning its output, its
+/// index in the original `Vec`, and the still-pending remainder so the caller can keep running or
+/// clean them up. Useful when the number of top-level futures (e.g. one per virtio queue) is only
+/// known at runtime, unlike the fixed-arity `select2`..`select6`.
+pub(crate) struct SelectAll<F: Future + Unpin> {
+    futures: Vec<Option<F>>,
+    new_futures: VecDeque<ExecutableFuture<()>>,
+}
+
+impl<F: Future + Unpin> SelectAll<F> {
+    pub fn new(futures: Vec<F>) -> SelectAll<F> {
+        SelectAll {
+            futures: futures.into_iter().map(Some).collect(),
+            new_futures: VecDeque::new(),
+        }
+    }
+}
+
+impl<F: Future + Unpin> FutureList for SelectAll<F> {
+    type Output = (F::Output, usize, Vec<F>);
+
+    fn poll_results(&mut self) -> Option<Self::Output> {
+        let waker = noop_waker_ref();
+        let mut cx = Context::from_waker(waker);
+
+        for i in 0..self.futures.len() {
+            let slot = &mut self.futures[i];
+            let output = match slot {
+                Some(fut) => match Pin::new(fut).poll(&mut cx) {
+                    Poll::Ready(output) => output,
+                    Poll::Pending => continue,
+                },
+                None => continue,
+            };
+            *slot = None;
+            let remaining = self.futures.drain(..).flatten().collect();
+            return Some((output, i, remaining));
+        }
+        None
+    }
+
+    fn any_ready(&self) -> bool {
+        false
+    }
+
+    fn futures_mut(&mut self) -> &mut VecDeque<ExecutableFuture<()>> {
+        &mut self.new_futures
+    }
+}
+
+/// Runs every future in `futures` until one resolves to `Ok`, returning that output plus the
+/// still-pending remainder. A future that resolves to `Err` is dropped and its error remembered;
+/// if every future fails, the last error observed is surfaced. Useful for device-probe or
+/// connection futures where any single success is enough.
+pub(crate) struct SelectOk<F: Future + Unpin> {
+    futures: Vec<Option<F>>,
+    new_futures: VecDeque<ExecutableFuture<()>>,
+}
+
+impl<F: Future + Unpin> SelectOk<F> {
+    pub fn new(futures: Vec<F>) -> SelectOk<F> {
+        SelectOk {
+            futures: futures.into_iter().map(Some).collect(),
+            new_futures: VecDeque::new(),
+        }
+    }
+}
+
+impl<F, T, E> FutureList for SelectOk<F>
+where
+    F: Future<Output = std::result::Result<T, E>> + Unpin,
+{
+    type Output = std::result::Result<(T, Vec<F>), E>;
+
+    fn poll_results(&mut self) -> Option<Self::Output> {
+        let waker = noop_waker_ref();
+        let mut cx = Context::from_waker(waker);
+
+        let mut last_err = None;
+        loop {
+            let mut made_progress = false;
+
+            for slot in self.futures.iter_mut() {
+                if slot.is_none() {
+                    continue;
+                }
+                let result = match Pin::new(slot.as_mut().unwrap()).poll(&mut cx) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => continue,
+                };
+                *slot = None;
+                made_progress = true;
+                match result {
+                    Ok(t) => {
+                        let remaining = self.futures.drain(..).flatten().collect();
+                        return Some(Ok((t, remaining)));
+                    }
+                    Err(e) => last_err = Some(e),
+                }
+            }
+
+            if !self.futures.iter().any(Option::is_some) {
+                // Every future has either succeeded (handled above) or failed; since we got here
+                // the set emptied without a success, so surface the last error observed.
+                return Some(Err(last_err.expect("empty future set without any errors")));
+            }
+
+            if !made_progress {
+                return None;
+            }
+        }
+    }
+
+    fn any_ready(&self) -> bool {
+        false
+    }
+
+    fn futures_mut(&mut self) -> &mut VecDeque<ExecutableFuture<()>> {
+        &mut self.new_futures
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2020 The Chromium OS Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A cloneable future, so that several top-level device futures can all await one shared
+//! completion (e.g. a backend handshake) instead of duplicating the work.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+enum State<F: Future> {
+    Pending { future: F, wakers: Vec<Waker> },
+    Complete(F::Output),
+}
+
+/// A `Future` that can be cloned; every clone polls the same underlying future and resolves to a
+/// clone of its output. Create one with `shared`.
+pub struct Shared<F: Future> {
+    state: Arc<Mutex<State<F>>>,
+}
+
+impl<F: Future> Clone for Shared<F> {
+    fn clone(&self) -> Self {
+        Shared {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<F: Future> Future for Shared<F>
+where
+    F::Output: Clone,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match &mut *state {
+            State::Complete(output) => Poll::Ready(output.clone()),
+            State::Pending { future, wakers } => {
+                // Safe because `future` is never moved out of the `Mutex` it lives behind; it is
+                // dropped in place when `state` is overwritten with `State::Complete` below.
+                let future = unsafe { Pin::new_unchecked(future) };
+                match future.poll(cx) {
+                    Poll::Ready(output) => {
+                        let to_wake = std::mem::take(wakers);
+                        *state = State::Complete(output.clone());
+                        drop(state);
+                        for waker in to_wake {
+                            waker.wake();
+                        }
+                        Poll::Ready(output)
+                    }
+                    Poll::Pending => {
+                        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                            wakers.push(cx.waker().clone());
+                        }
+                        Poll::Pending
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Wraps `fut` in a `Shared`, letting multiple clones all await its single completion. Whichever
+/// clone is polled while the inner future is pending drives it and registers its waker; once it
+/// resolves, every registered waker is woken and all clones resolve to a clone of the output.
+pub fn shared<F: Future>(fut: F) -> Shared<F>
+where
+    F::Output: Clone,
+{
+    Shared {
+        state: Arc::new(Mutex::new(State::Pending {
+            future: fut,
+            wakers: Vec::new(),
+        })),
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2020 The Chromium OS Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! An async `timerfd`, usable both to sleep for a bounded duration and to bound how long another
+//! future is allowed to wait, driven by the same executor wakers as the rest of this crate.
+
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::{select, Either};
+use futures::pin_mut;
+use sys_util::TimerFd;
+
+use crate::{add_read_waker, cancel_waker, WakerToken};
+
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to create or arm the timerfd.
+    Timer(sys_util::Error),
+    /// Failed to register the timerfd with the executor.
+    AddingWaker(crate::Error),
+}
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+
+        match self {
+            Timer(e) => write!(f, "Failed to create or arm the timerfd: {}", e),
+            AddingWaker(e) => write!(f, "Failed to register the timerfd with the executor: {}", e),
+        }
+    }
+}
+
+/// An async wrapper around a `timerfd` that fires once after a duration, or repeatedly on an
+/// interval.
+pub struct TimerAsync {
+    timer: TimerFd,
+    token: Option<WakerToken>,
+}
+
+impl TimerAsync {
+    /// Creates a `TimerAsync` that fires once, `dur` from now.
+    pub fn sleep(dur: Duration) -> Result<TimerAsync> {
+        let mut timer = TimerFd::new().map_err(Error::Timer)?;
+        timer.reset(dur, None).map_err(Error::Timer)?;
+        Ok(TimerAsync { timer, token: None })
+    }
+
+    /// Creates a `TimerAsync` that fires every `dur`, starting `dur` from now.
+    pub fn interval(dur: Duration) -> Result<TimerAsync> {
+        let mut timer = TimerFd::new().map_err(Error::Timer)?;
+        timer.reset(dur, Some(dur)).map_err(Error::Timer)?;
+        Ok(TimerAsync { timer, token: None })
+    }
+
+    /// Waits for the timer to fire once. For an interval timer, each call waits for the next
+    /// tick.
+    pub async fn wait(&mut self) -> Result<()> {
+        WaitFuture { timer: self }.await
+    }
+}
+
+// Polls the wrapped `TimerAsync`'s fd for readability, then reaps the expiration count so the
+// next `wait` doesn't immediately fire again on a repeating timer.
+struct WaitFuture<'a> {
+    timer: &'a mut TimerAsync,
+}
+
+impl<'a> Future for WaitFuture<'a> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.timer.timer.wait() {
+            Ok(_) => {
+                this.timer.token = None;
+                Poll::Ready(Ok(()))
+            }
+            Err(e) if e.errno() == libc::EAGAIN => {
+                this.timer.token = Some(
+                    add_read_waker(this.timer.timer.as_raw_fd(), cx.waker().clone())
+                        .map_err(Error::AddingWaker)?,
+                );
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(Error::Timer(e))),
+        }
+    }
+}
+
+impl Drop for TimerAsync {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            let _ = cancel_waker(token);
+        }
+    }
+}
+
+/// The future passed to `timeout` did not resolve before the deadline elapsed.
+#[derive(Debug)]
+pub struct Elapsed;
+
+/// Races `fut` against a one-shot timer of `dur`, resolving to `Ok(Ok(fut's output))` if it
+/// finishes first or `Ok(Err(Elapsed))` if the timer fires first. Returns `Err` only if the timer
+/// itself couldn't be set up or waited on (e.g. the process is out of file descriptors), which is
+/// a recoverable condition the caller should see rather than one that aborts the process.
+pub async fn timeout<F: Future>(
+    fut: F,
+    dur: Duration,
+) -> Result<std::result::Result<F::Output, Elapsed>> {
+    let mut timer = TimerAsync::sleep(dur)?;
+    let timer_wait = timer.wait();
+    pin_mut!(fut);
+    pin_mut!(timer_wait);
+    match select(fut, timer_wait).await {
+        Either::Left((output, _)) => Ok(Ok(output)),
+        Either::Right((Ok(()), _)) => Ok(Err(Elapsed)),
+        Either::Right((Err(e), _)) => Err(e),
+    }
+}
@@ -16,7 +16,7 @@ use std::fs::File;
 use std::future::Future;
 use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
 use std::pin::Pin;
-use std::task::Waker;
+use std::task::{Context, Poll, Waker};
 
 use io_uring::URingContext;
 use sys_util::WatchingEvents;
@@ -41,6 +41,8 @@ pub enum Error {
     URingContextError(io_uring::Error),
     /// Failed to submit or wait for io_uring events.
     URingEnter(io_uring::Error),
+    /// No result is available yet for the given operation.
+    NoResultAvailable,
 }
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -60,6 +62,7 @@ impl Display for Error {
             SubmittingWaker(e) => write!(f, "Error adding to the URing context: {}.", e),
             URingContextError(e) => write!(f, "URingContext failure: {}", e),
             URingEnter(e) => write!(f, "URing::enter: {}", e),
+            NoResultAvailable => write!(f, "No result is available for the given operation yet."),
         }
     }
 }
@@ -114,6 +117,64 @@ pub fn cancel_waker(token: WakerToken) -> Result<()> {
     })
 }
 
+/// Submits a `readv` of `fd` at `offset` into `buf`, waking `waker` once the read completes.
+/// Ownership of `buf` is transferred to the executor so it is guaranteed to stay alive for as
+/// long as the kernel holds a pointer to it; it is handed back by `take_result`.
+/// Returns a `WakerToken` that identifies the operation to `take_result`.
+pub fn add_read_op(fd: RawFd, offset: u64, buf: Vec<u8>, waker: Waker) -> Result<WakerToken> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if let Some(state) = state.as_mut() {
+            state.add_read_op(fd, offset, buf, waker)
+        } else {
+            Err(Error::InvalidContext)
+        }
+    })
+}
+
+/// Submits a `writev` of `fd` at `offset` from `buf`, waking `waker` once the write completes.
+/// Ownership of `buf` is transferred to the executor so it is guaranteed to stay alive for as
+/// long as the kernel holds a pointer to it; it is handed back by `take_result`.
+/// Returns a `WakerToken` that identifies the operation to `take_result`.
+pub fn add_write_op(fd: RawFd, offset: u64, buf: Vec<u8>, waker: Waker) -> Result<WakerToken> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if let Some(state) = state.as_mut() {
+            state.add_write_op(fd, offset, buf, waker)
+        } else {
+            Err(Error::InvalidContext)
+        }
+    })
+}
+
+/// Submits an `fsync` of `fd`, waking `waker` once the sync completes.
+/// Returns a `WakerToken` that identifies the operation to `take_result`.
+pub fn add_fsync(fd: RawFd, waker: Waker) -> Result<WakerToken> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if let Some(state) = state.as_mut() {
+            state.add_fsync(fd, waker)
+        } else {
+            Err(Error::InvalidContext)
+        }
+    })
+}
+
+/// Returns the `res` value of the completed operation identified by `token` along with the
+/// buffer that was passed to `add_read_op`/`add_write_op` (empty for `add_fsync`), consuming the
+/// result. Calling it before the op has completed returns `Error::NoResultAvailable` and leaves
+/// the op registered, so it can be called again once the waker has been woken.
+pub fn take_result(token: WakerToken) -> Result<(i64, Vec<u8>)> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if let Some(state) = state.as_mut() {
+            state.take_result(token)
+        } else {
+            Err(Error::InvalidContext)
+        }
+    })
+}
+
 /// Adds a new top level future to the Executor.
 /// These futures must return `()`, indicating they are intended to create side-effects only.
 pub fn add_future(future: Pin<Box<dyn Future<Output = ()>>>) -> Result<()> {
@@ -128,10 +189,39 @@ pub fn add_future(future: Pin<Box<dyn Future<Output = ()>>>) -> Result<()> {
     })
 }
 
+// The kind of uring operation a registered token represents. `Poll` is a readiness
+// registration with no result of its own; the rest are completion ops whose CQE `res` is the
+// thing the awaiting future actually cares about.
+enum OpType {
+    Poll(WatchingEvents),
+    Read,
+    Write,
+    Fsync,
+}
+
+// Bookkeeping kept for a single outstanding operation, from submission until its waker fires.
+// `buf` is the buffer the kernel was handed for a `Read`/`Write` op, kept here (rather than
+// borrowed from the caller) so it can't be dropped or reused out from under an in-flight
+// operation; it is handed back to the caller through `take_result`. Empty for `Poll`/`Fsync`.
+struct OpData {
+    file: File,
+    op: OpType,
+    waker: Waker,
+    buf: Vec<u8>,
+}
+
 // Tracks active wakers and associates wakers with the futures that registered them.
 struct RingWakerState {
     ctx: URingContext,
-    token_map: BTreeMap<u64, (File, WatchingEvents, Waker)>,
+    token_map: BTreeMap<u64, OpData>,
+    // Results of completion ops (read/write/fsync) that have fired but not yet been collected
+    // by the future via `take_result`.
+    results: BTreeMap<u64, (i64, Vec<u8>)>,
+    // Completion ops (read/write/fsync) whose waker was cancelled before the kernel finished
+    // them. The kernel may still be reading from or writing into `buf` until the matching CQE
+    // shows up in `wait_wake_event`, so `file`/`buf` are kept alive here rather than dropped at
+    // cancel time.
+    cancelled_ops: BTreeMap<u64, OpData>,
     next_token: u64, // Next token for adding to the context.
     new_futures: VecDeque<ExecutableFuture<()>>,
 }
@@ -141,6 +231,8 @@ impl RingWakerState {
         Ok(RingWakerState {
             ctx: URingContext::new(256).map_err(Error::CreatingContext)?,
             token_map: BTreeMap::new(),
+            results: BTreeMap::new(),
+            cancelled_ops: BTreeMap::new(),
             next_token: 0,
             new_futures: VecDeque::new(),
         })
@@ -157,28 +249,132 @@ impl RingWakerState {
             .add_poll_fd(duped_fd.as_raw_fd(), &events, self.next_token)
             .map_err(Error::SubmittingWaker)?;
         let next_token = self.next_token;
-        self.token_map.insert(next_token, (duped_fd, events, waker));
+        self.token_map.insert(
+            next_token,
+            OpData {
+                file: duped_fd,
+                op: OpType::Poll(events),
+                waker,
+                buf: Vec::new(),
+            },
+        );
+        self.next_token += 1;
+        Ok(WakerToken(next_token))
+    }
+
+    // Submits a `readv` of `fd` at `offset` into `buf`, to wake `waker` on completion. `buf` is
+    // moved into the `OpData` kept in `token_map` so it can't be dropped or reused while the
+    // kernel still holds a pointer to it; `take_result` hands it back once the op completes.
+    fn add_read_op(&mut self, fd: RawFd, offset: u64, mut buf: Vec<u8>, waker: Waker) -> Result<WakerToken> {
+        let duped_fd = unsafe {
+            // Safe because duplicating an FD doesn't affect memory safety, and the dup'd FD is
+            // only used to keep the original alive for the kernel's benefit.
+            File::from_raw_fd(dup_fd(fd)?)
+        };
+        self.ctx
+            .add_read(duped_fd.as_raw_fd(), &mut buf, offset, self.next_token)
+            .map_err(Error::SubmittingWaker)?;
+        let next_token = self.next_token;
+        self.token_map.insert(
+            next_token,
+            OpData {
+                file: duped_fd,
+                op: OpType::Read,
+                waker,
+                buf,
+            },
+        );
+        self.next_token += 1;
+        Ok(WakerToken(next_token))
+    }
+
+    // Submits a `writev` of `fd` at `offset` from `buf`, to wake `waker` on completion. `buf` is
+    // moved into the `OpData` kept in `token_map` for the same reason as `add_read_op`.
+    fn add_write_op(&mut self, fd: RawFd, offset: u64, buf: Vec<u8>, waker: Waker) -> Result<WakerToken> {
+        let duped_fd = unsafe {
+            // Safe because duplicating an FD doesn't affect memory safety, and the dup'd FD is
+            // only used to keep the original alive for the kernel's benefit.
+            File::from_raw_fd(dup_fd(fd)?)
+        };
+        self.ctx
+            .add_write(duped_fd.as_raw_fd(), &buf, offset, self.next_token)
+            .map_err(Error::SubmittingWaker)?;
+        let next_token = self.next_token;
+        self.token_map.insert(
+            next_token,
+            OpData {
+                file: duped_fd,
+                op: OpType::Write,
+                waker,
+                buf,
+            },
+        );
+        self.next_token += 1;
+        Ok(WakerToken(next_token))
+    }
+
+    // Submits an `fsync` of `fd`, to wake `waker` on completion.
+    fn add_fsync(&mut self, fd: RawFd, waker: Waker) -> Result<WakerToken> {
+        let duped_fd = unsafe {
+            // Safe because duplicating an FD doesn't affect memory safety, and the dup'd FD
+            // will only be used for the fsync operation.
+            File::from_raw_fd(dup_fd(fd)?)
+        };
+        self.ctx
+            .add_fsync(duped_fd.as_raw_fd(), self.next_token)
+            .map_err(Error::SubmittingWaker)?;
+        let next_token = self.next_token;
+        self.token_map.insert(
+            next_token,
+            OpData {
+                file: duped_fd,
+                op: OpType::Fsync,
+                waker,
+                buf: Vec::new(),
+            },
+        );
         self.next_token += 1;
         Ok(WakerToken(next_token))
     }
 
     // Remove the waker for the given token if it hasn't fired yet.
     fn cancel_waker(&mut self, token: WakerToken) -> Result<()> {
-        if let Some((file, events, _waker)) = self.token_map.remove(&token.0) {
-            self.ctx
-                .remove_poll_fd(file.as_raw_fd(), &events, token.0)
-                .map_err(Error::RemovingWaker)?
+        if let Some(op_data) = self.token_map.remove(&token.0) {
+            if let OpType::Poll(events) = &op_data.op {
+                self.ctx
+                    .remove_poll_fd(op_data.file.as_raw_fd(), events, token.0)
+                    .map_err(Error::RemovingWaker)?;
+            } else {
+                // Read/Write/Fsync ops are already in flight in the kernel and can't be cancelled
+                // there; keep `file`/`buf` alive until the matching CQE shows up in
+                // `wait_wake_event`, since the kernel may still be reading from or writing into
+                // `buf` until then. Dropping `op_data` here would free it out from under that
+                // in-flight read or write.
+                self.cancelled_ops.insert(token.0, op_data);
+            }
         }
+        self.results.remove(&token.0);
         Ok(())
     }
 
+    // Returns the result of a completed operation along with its buffer, consuming it.
+    fn take_result(&mut self, token: WakerToken) -> Result<(i64, Vec<u8>)> {
+        self.results.remove(&token.0).ok_or(Error::NoResultAvailable)
+    }
+
     // Waits until one of the FDs is readable and wakes the associated waker.
     fn wait_wake_event(&mut self) -> Result<()> {
         let events = self.ctx.wait().map_err(Error::URingEnter)?;
-        for (token, _result) in events {
-            // TODO - store the result and make accessible to the future.
-            if let Some((_file, _event, waker)) = self.token_map.remove(&token) {
-                waker.wake_by_ref();
+        for (token, result) in events {
+            if let Some(op_data) = self.token_map.remove(&token) {
+                if !matches!(op_data.op, OpType::Poll(_)) {
+                    self.results.insert(token, (result, op_data.buf));
+                }
+                op_data.waker.wake_by_ref();
+            } else if self.cancelled_ops.remove(&token).is_some() {
+                // The future that owned this op was dropped before the kernel finished it; now
+                // that the CQE has arrived the kernel is done with `file`/`buf`, so it's safe to
+                // drop them along with the rest of `op_data`.
             }
         }
         Ok(())
@@ -333,6 +529,76 @@ mod test {
         });
     }
 
+    #[test]
+    fn cancel_read_op_in_flight() {
+        struct ReadFut {
+            fd: RawFd,
+            token: Option<WakerToken>,
+        }
+
+        impl Future for ReadFut {
+            type Output = ();
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                if self.token.is_none() {
+                    self.token =
+                        Some(add_read_op(self.fd, 0, vec![0u8; 8], cx.waker().clone()).unwrap());
+                }
+                Poll::Pending
+            }
+        }
+
+        impl Drop for ReadFut {
+            fn drop(&mut self) {
+                if let Some(token) = self.token.take() {
+                    cancel_waker(token).unwrap();
+                }
+            }
+        }
+
+        async fn do_test(fd: RawFd) {
+            let done = async { 5usize };
+            let pending = ReadFut { fd, token: None };
+            pin_mut!(done);
+            pin_mut!(pending);
+            match futures::future::select(pending, done).await {
+                Either::Right((5, _pending)) => (),
+                _ => panic!("unexpected select result"),
+            }
+        }
+
+        // `w` stays open so that writing to it below can complete the cancelled read; the read
+        // itself goes through the dup of `r` that `add_read_op` keeps in the executor.
+        let (r, mut w) = sys_util::pipe(true).unwrap();
+
+        let mut ex =
+            URingExecutor::new(crate::UnitFutures::new()).expect("Failed creating executor");
+        add_future(Box::pin(do_test(r.as_raw_fd()))).unwrap();
+        ex.run().unwrap();
+
+        // `do_test` finished (and so dropped its still-pending `ReadFut`) before the pipe had any
+        // data to read, so the read op's token was cancelled while still in flight: its `OpData`
+        // must have been kept alive in `cancelled_ops`, not freed out from under the kernel.
+        STATE.with(|state| {
+            let state = state.borrow();
+            let state = state.as_ref().unwrap();
+            assert!(state.token_map.is_empty());
+            assert_eq!(state.cancelled_ops.len(), 1);
+        });
+
+        // Now let the kernel actually complete the cancelled read; reaping that completion must
+        // drop the orphaned `file`/`buf` cleanly instead of touching already-freed memory.
+        use std::io::Write;
+        w.write_all(&[0u8; 8]).unwrap();
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.as_mut().unwrap().wait_wake_event().unwrap();
+        });
+        STATE.with(|state| {
+            let state = state.borrow();
+            assert!(state.as_ref().unwrap().cancelled_ops.is_empty());
+        });
+    }
+
     #[test]
     fn run() {
         // Example of starting the framework and running a future:
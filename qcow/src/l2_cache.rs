@@ -3,6 +3,7 @@
 // found in the LICENSE file.
 
 use std;
+use std::cell::Cell;
 use std::collections::HashMap;
 
 #[derive(Debug)]
@@ -15,6 +16,9 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub struct L2Table {
     cluster_addrs: Vec<u64>,
     dirty: bool,
+    // Set to the cache's clock value each time this table is looked up or inserted, so the cache
+    // can find the least-recently-used table without needing a linked list.
+    last_access: Cell<u64>,
 }
 
 impl L2Table {
@@ -22,6 +26,7 @@ impl L2Table {
         L2Table {
             cluster_addrs: vec![0, table_size as u64],
             dirty: true,
+            last_access: Cell::new(0),
         }
     }
 
@@ -29,6 +34,7 @@ impl L2Table {
         L2Table {
             cluster_addrs: addrs,
             dirty: false,
+            last_access: Cell::new(0),
         }
     }
 
@@ -52,6 +58,9 @@ impl L2Table {
 pub struct L2Cache {
     tables: HashMap<usize, L2Table>,
     table_size: usize,
+    // Monotonically increasing counter, stamped onto a table's `last_access` on every touch.
+    // The table with the smallest `last_access` is the least-recently-used one.
+    clock: Cell<u64>,
 }
 
 impl L2Cache {
@@ -59,11 +68,21 @@ impl L2Cache {
         L2Cache {
             tables: HashMap::with_capacity(capacity),
             table_size,
+            clock: Cell::new(0),
         }
     }
-    
+
+    // Advances the clock and returns the new value, to be stamped onto a table's `last_access`.
+    fn tick(&self) -> u64 {
+        let next = self.clock.get() + 1;
+        self.clock.set(next);
+        next
+    }
+
     pub fn get_table(&self, l1_index: usize) -> Option<&L2Table> {
-        self.tables.get(&l1_index)
+        let table = self.tables.get(&l1_index)?;
+        table.last_access.set(self.tick());
+        Some(table)
     }
 
     pub fn create_table(&self) -> L2Table {
@@ -74,15 +93,26 @@ impl L2Cache {
         self.tables.remove(&l1_index).map(|mut t| {t.dirty = true; t})
     }
 
+    // Returns the key of the least-recently-used table, if any are cached.
+    fn lru_key(&self) -> Option<usize> {
+        self.tables
+            .iter()
+            .min_by_key(|(_, table)| table.last_access.get())
+            .map(|(&k, _)| k)
+    }
+
+    /// Inserts `table` for `l1_index`, evicting the least-recently-used table if the cache is at
+    /// capacity. The evicted table is returned so the caller can write it back if it is dirty,
+    /// rather than silently dropping (and losing) its metadata.
     pub fn insert(&mut self, l1_index: usize, table: L2Table) -> Option<L2Table> {
         let evicted = if self.tables.len() == self.tables.capacity() {
-            // TODO(dgreid) smarter eviction
-            let k = self.tables.keys().nth(0).unwrap().clone();
+            let k = self.lru_key().expect("capacity is non-zero but cache is empty");
             self.tables.remove(&k)
         } else {
             None
         };
 
+        table.last_access.set(self.tick());
         self.tables.insert(l1_index, table);
 
         evicted
@@ -96,7 +126,60 @@ impl L2Cache {
         Ok(self.insert(l1_index, L2Table::from_vec(addrs)))
     }
 
+    /// Returns the dirty tables in least-recently-used order, so flushes can be batched starting
+    /// with the tables most likely to be evicted next.
     pub fn dirty_iter_mut(&mut self) -> impl Iterator<Item = &L2Table> {
-        self.tables.iter().filter_map(|(k, v)| if v.dirty { Some(v) } else { None })
+        let mut dirty: Vec<&L2Table> = self.tables.values().filter(|v| v.dirty).collect();
+        dirty.sort_by_key(|table| table.last_access.get());
+        dirty.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tags a freshly created table with a marker value so tests can tell tables apart after
+    // they come back out of the cache (e.g. as the return value of `insert`).
+    fn tagged_table(cache: &L2Cache, marker: u64) -> L2Table {
+        let mut table = cache.create_table();
+        table.set(0, marker);
+        table
+    }
+
+    #[test]
+    fn insert_evicts_least_recently_used() {
+        // `HashMap::with_capacity(3)` rounds up to a usable capacity of 3 as well, so the cache
+        // is full after the third insert and the fourth triggers eviction.
+        let mut cache = L2Cache::new(4, 3);
+        cache.insert(0, tagged_table(&cache, 100));
+        cache.insert(1, tagged_table(&cache, 200));
+        cache.insert(2, tagged_table(&cache, 300));
+
+        // Touch table 0 so table 1 becomes the least-recently-used entry, not table 0.
+        cache.get_table(0);
+
+        let evicted = cache.insert(3, tagged_table(&cache, 400));
+
+        assert_eq!(evicted.expect("insert should have evicted a table").get(0), 200);
+        assert!(cache.get_table(0).is_some());
+        assert!(cache.get_table(1).is_none());
+        assert!(cache.get_table(2).is_some());
+        assert!(cache.get_table(3).is_some());
+    }
+
+    #[test]
+    fn dirty_iter_mut_returns_least_recently_used_first() {
+        let mut cache = L2Cache::new(4, 3);
+        cache.insert(0, tagged_table(&cache, 100));
+        cache.insert(1, tagged_table(&cache, 200));
+        cache.insert(2, tagged_table(&cache, 300));
+
+        // Touch 0 then 1, leaving 2 as the least-recently-used table.
+        cache.get_table(0);
+        cache.get_table(1);
+
+        let markers: Vec<u64> = cache.dirty_iter_mut().map(|table| table.get(0)).collect();
+        assert_eq!(markers, vec![300, 100, 200]);
     }
-}
\ No newline at end of file
+}